@@ -21,9 +21,6 @@
  *   Software.
  */
 
-use std;
-
-
 #[derive(Clone,Default)]
 pub struct BinaryArraySet<E> {
 	
@@ -82,7 +79,7 @@ impl<E: std::cmp::Ord> BinaryArraySet<E> {
 	
 	// Runs in amortized O(1) time, worst-case O(n) time
 	pub fn insert_unique(&mut self, val: E) {
-		assert!(self.size < std::usize::MAX, "Maximum size reached");
+		assert!(self.size < usize::MAX, "Maximum size reached");
 		self.size += 1;
 		let mut toput: Vec<E> = vec![val];
 		for vals in &mut self.values {
@@ -93,13 +90,50 @@ impl<E: std::cmp::Ord> BinaryArraySet<E> {
 			
 			// Merge two sorted arrays
 			assert_eq!(vals.len(), toput.len());
-			assert!(vals.len() <= std::usize::MAX / 2);
+			assert!(vals.len() <= usize::MAX / 2);
 			toput = BinaryArraySet::merge_vecs(vals, toput);
 		}
 		self.values.push(toput);
 	}
-	
-	
+
+
+	// Runs in O((log n)^2) time to find val, plus worst-case O(n) time to
+	// restructure the buckets
+	pub fn remove(&mut self, val: &E) -> bool {
+		let k = match self.values.iter().position(|vals| vals.binary_search(val).is_ok()) {
+			Some(k) => k,
+			None => return false,
+		};
+
+		match self.values[..k].iter().position(|vals| !vals.is_empty()) {
+			None => {
+				// Bucket k is the lowest non-empty bucket: remove val from it,
+				// then split the 2^k - 1 remaining elements into buckets 0..k.
+				let i = self.values[k].binary_search(val).unwrap();
+				self.values[k].remove(i);
+				let remainder = std::mem::take(&mut self.values[k]);
+				BinaryArraySet::fill_buckets(&mut self.values[..k], remainder);
+			},
+			Some(j) => {
+				// Disassemble the lowest non-empty bucket j < k: pull out one
+				// element u, split the rest into the now-empty buckets 0..j,
+				// then swap u into bucket k in place of val.
+				let mut bucket = std::mem::take(&mut self.values[j]);
+				let u = bucket.remove(0);
+				BinaryArraySet::fill_buckets(&mut self.values[..j], bucket);
+
+				let i = self.values[k].binary_search(val).unwrap();
+				self.values[k].remove(i);
+				let pos = self.values[k].binary_search(&u).unwrap_err();
+				self.values[k].insert(pos, u);
+			},
+		}
+
+		self.size -= 1;
+		true
+	}
+
+
 	pub fn check_structure(&self) {
 		let mut sum: usize = 0;
 		for (i, vals) in self.values.iter().enumerate() {
@@ -139,7 +173,227 @@ impl<E: std::cmp::Ord> BinaryArraySet<E> {
 		}
 		result
 	}
-	
+
+
+	// (Private) Fills buckets[i] (for every i in range) with 2^i elements taken
+	// in order from the front of 'sorted', given that sorted.len() equals
+	// 2^buckets.len() - 1. Used by remove() to re-partition a disassembled bucket.
+	fn fill_buckets(buckets: &mut [Vec<E>], sorted: Vec<E>) {
+		let mut iter = sorted.into_iter();
+		for (i, bucket) in buckets.iter_mut().enumerate() {
+			*bucket = iter.by_ref().take(1 << i).collect();
+		}
+	}
+
+
+	// (Private) Returns references to every element in ascending order, computed
+	// in O(n) total time by merging the buckets (each already sorted) from
+	// smallest to largest. Used to serialize the set as a flat sorted sequence.
+	fn iter_sorted(&self) -> Vec<&E> {
+		let mut result = Vec::<&E>::with_capacity(self.size);
+		for vals in &self.values {
+			if !vals.is_empty() {
+				result = BinaryArraySet::merge_sorted_refs(&result, vals);
+			}
+		}
+		result
+	}
+
+
+	// (Private) Assuming that xs and ys are both in ascending order, returns a
+	// new vector of references to all their elements, also in ascending order.
+	fn merge_sorted_refs<'a>(xs: &[&'a E], ys: &'a [E]) -> Vec<&'a E> {
+		let mut result = Vec::<&E>::with_capacity(xs.len() + ys.len());
+		let mut xiter = xs.iter().copied();
+		let mut yiter = ys.iter();
+		let mut xnext = xiter.next();
+		let mut ynext = yiter.next();
+		loop {
+			let takex: bool = match (xnext, ynext) {
+				(None, None) => break,
+				(_, None) => true,
+				(None, _) => false,
+				(Some(x), Some(y)) => *x <= *y,
+			};
+			if takex {
+				result.push(xnext.unwrap());
+				xnext = xiter.next();
+			} else {
+				result.push(ynext.unwrap());
+				ynext = yiter.next();
+			}
+		}
+		result
+	}
+
+
+	// (Private) Rebuilds a set directly from a vector already in strictly
+	// ascending order, partitioning it into the values[i] buckets matching the
+	// set bits of its length. Runs in O(n) time, skipping the per-element
+	// duplicate checks and merging that insert_unique() does.
+	fn from_sorted_vec(sorted: Vec<E>) -> Self {
+		let size = sorted.len();
+		let mut iter = sorted.into_iter();
+		let mut values = Vec::<Vec<E>>::new();
+		let mut bit: usize = 1;
+		while bit <= size {
+			values.push(if size & bit != 0 { iter.by_ref().take(bit).collect() } else { Vec::new() });
+			bit <<= 1;
+		}
+		Self { values, size }
+	}
+
+
+	// (Private) Consumes the set and returns all its elements in ascending
+	// order, computed in O(n) total time by merging the buckets (each already
+	// sorted) from smallest to largest.
+	fn into_sorted_vec(self) -> Vec<E> {
+		let mut result = Vec::<E>::with_capacity(self.size);
+		for vals in self.values {
+			if !vals.is_empty() {
+				result = BinaryArraySet::merge_vecs(&mut result, vals);
+			}
+		}
+		result
+	}
+
+
+	// Runs in O(n) time, where n = self.len() + other.len()
+	pub fn union(self, other: Self) -> Self {
+		let merged = BinaryArraySet::merge_union(self.into_sorted_vec(), other.into_sorted_vec());
+		BinaryArraySet::from_sorted_vec(merged)
+	}
+
+
+	// Runs in O(n) time, where n = self.len() + other.len()
+	pub fn intersection(self, other: &Self) -> Self {
+		let merged = BinaryArraySet::merge_intersection(self.into_sorted_vec(), other.iter_sorted());
+		BinaryArraySet::from_sorted_vec(merged)
+	}
+
+
+	// Runs in O(n) time, where n = self.len() + other.len()
+	pub fn difference(self, other: &Self) -> Self {
+		let merged = BinaryArraySet::merge_difference(self.into_sorted_vec(), other.iter_sorted());
+		BinaryArraySet::from_sorted_vec(merged)
+	}
+
+
+	// Runs in O(n) time, where n = self.len() + other.len()
+	pub fn symmetric_difference(self, other: Self) -> Self {
+		let merged = BinaryArraySet::merge_symmetric_difference(self.into_sorted_vec(), other.into_sorted_vec());
+		BinaryArraySet::from_sorted_vec(merged)
+	}
+
+
+	// (Private) Merges two sorted vectors into one sorted vector with no
+	// duplicates, keeping the element from 'a' when both sides have an equal one.
+	fn merge_union(a: Vec<E>, b: Vec<E>) -> Vec<E> {
+		let mut result = Vec::<E>::with_capacity(a.len() + b.len());
+		let mut aiter = a.into_iter();
+		let mut biter = b.into_iter();
+		let mut anext = aiter.next();
+		let mut bnext = biter.next();
+		loop {
+			match (anext, bnext) {
+				(None, None) => break,
+				(Some(x), None) => { result.push(x); anext = aiter.next(); bnext = None; },
+				(None, Some(y)) => { result.push(y); bnext = biter.next(); anext = None; },
+				(Some(x), Some(y)) => {
+					match x.cmp(&y) {
+						std::cmp::Ordering::Less => { result.push(x); anext = aiter.next(); bnext = Some(y); },
+						std::cmp::Ordering::Greater => { result.push(y); bnext = biter.next(); anext = Some(x); },
+						std::cmp::Ordering::Equal => { result.push(x); anext = aiter.next(); bnext = biter.next(); },
+					}
+				},
+			}
+		}
+		result
+	}
+
+
+	// (Private) Walks two sorted sequences and keeps only the elements of 'a'
+	// that also occur in 'b'.
+	fn merge_intersection(a: Vec<E>, b: Vec<&E>) -> Vec<E> {
+		let mut result = Vec::<E>::new();
+		let mut aiter = a.into_iter();
+		let mut biter = b.into_iter();
+		let mut anext = aiter.next();
+		let mut bnext = biter.next();
+		while let (Some(x), Some(y)) = (anext, bnext) {
+			match x.cmp(y) {
+				std::cmp::Ordering::Less => { anext = aiter.next(); bnext = Some(y); },
+				std::cmp::Ordering::Greater => { anext = Some(x); bnext = biter.next(); },
+				std::cmp::Ordering::Equal => { result.push(x); anext = aiter.next(); bnext = biter.next(); },
+			}
+		}
+		result
+	}
+
+
+	// (Private) Walks two sorted sequences and keeps only the elements of 'a'
+	// that do not occur in 'b'.
+	fn merge_difference(a: Vec<E>, b: Vec<&E>) -> Vec<E> {
+		let mut result = Vec::<E>::with_capacity(a.len());
+		let mut aiter = a.into_iter();
+		let mut biter = b.into_iter();
+		let mut anext = aiter.next();
+		let mut bnext = biter.next();
+		loop {
+			match (anext, bnext) {
+				(None, _) => break,
+				(Some(x), None) => { result.push(x); anext = aiter.next(); },
+				(Some(x), Some(y)) => {
+					match x.cmp(y) {
+						std::cmp::Ordering::Less => { result.push(x); anext = aiter.next(); bnext = Some(y); },
+						std::cmp::Ordering::Greater => { anext = Some(x); bnext = biter.next(); },
+						std::cmp::Ordering::Equal => { anext = aiter.next(); bnext = biter.next(); },
+					}
+				},
+			}
+		}
+		result
+	}
+
+
+	// (Private) Merges two sorted vectors, keeping only the elements that occur
+	// in exactly one of them.
+	fn merge_symmetric_difference(a: Vec<E>, b: Vec<E>) -> Vec<E> {
+		let mut result = Vec::<E>::new();
+		let mut aiter = a.into_iter();
+		let mut biter = b.into_iter();
+		let mut anext = aiter.next();
+		let mut bnext = biter.next();
+		loop {
+			match (anext, bnext) {
+				(None, None) => break,
+				(Some(x), None) => { result.push(x); anext = aiter.next(); bnext = None; },
+				(None, Some(y)) => { result.push(y); bnext = biter.next(); anext = None; },
+				(Some(x), Some(y)) => {
+					match x.cmp(&y) {
+						std::cmp::Ordering::Less => { result.push(x); anext = aiter.next(); bnext = Some(y); },
+						std::cmp::Ordering::Greater => { result.push(y); bnext = biter.next(); anext = Some(x); },
+						std::cmp::Ordering::Equal => { anext = aiter.next(); bnext = biter.next(); },
+					}
+				},
+			}
+		}
+		result
+	}
+
+}
+
+
+// Builds a set from an arbitrary iterator in O(n log n) time: sorts and dedups
+// the items once, then partitions them directly into buckets (see
+// from_sorted_vec), instead of inserting one at a time at O(n (log n)^2).
+impl<E: std::cmp::Ord> std::iter::FromIterator<E> for BinaryArraySet<E> {
+	fn from_iter<T: IntoIterator<Item = E>>(iter: T) -> Self {
+		let mut items: Vec<E> = iter.into_iter().collect();
+		items.sort();
+		items.dedup();
+		BinaryArraySet::from_sorted_vec(items)
+	}
 }
 
 
@@ -151,7 +405,7 @@ impl<'a, E> IntoIterator for &'a BinaryArraySet<E> {
 	type IntoIter = Iter<'a, E>;
 	
 	fn into_iter(self) -> Self::IntoIter {
-		Iter::<E>::new(&self)
+		Iter::<E>::new(self)
 	}
 }
 
@@ -201,9 +455,67 @@ impl<'a, E> Iterator for Iter<'a, E> {
 	fn size_hint(&self) -> (usize,Option<usize>) {
 		(self.count, Some(self.count))
 	}
-	
+
 	fn count(self) -> usize {
 		self.count
 	}
-	
+
+}
+
+
+
+/*---- Serde support ----*/
+
+#[cfg(feature = "serde")]
+impl<E: std::cmp::Ord + serde::Serialize> serde::Serialize for BinaryArraySet<E> {
+
+	// Serializes as a flat, length-prefixed sequence of elements in ascending
+	// order, obtained by merging the already-sorted buckets in O(n) time.
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		use serde::ser::SerializeSeq;
+		let sorted = self.iter_sorted();
+		let mut seq = serializer.serialize_seq(Some(sorted.len()))?;
+		for val in sorted {
+			seq.serialize_element(val)?;
+		}
+		seq.end()
+	}
+
+}
+
+
+#[cfg(feature = "serde")]
+impl<'de, E: std::cmp::Ord + serde::Deserialize<'de>> serde::Deserialize<'de> for BinaryArraySet<E> {
+
+	// Deserializes a flat sequence as written by Serialize above. Collects the
+	// whole sequence first and verifies it is strictly ascending, then rebuilds
+	// the bucket layout directly (see from_sorted_vec) rather than inserting one
+	// element at a time, so loading an n-element set runs in O(n) time.
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct SetVisitor<E>(std::marker::PhantomData<E>);
+
+		impl<'de, E: std::cmp::Ord + serde::Deserialize<'de>> serde::de::Visitor<'de> for SetVisitor<E> {
+			type Value = BinaryArraySet<E>;
+
+			fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+				write!(f, "a sequence of elements in strictly ascending order")
+			}
+
+			fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+				let mut elems = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+				while let Some(val) = seq.next_element()? {
+					elems.push(val);
+				}
+				for i in 1 .. elems.len() {
+					if elems[i - 1] >= elems[i] {
+						return Err(serde::de::Error::custom("Elements are not in strictly ascending order"));
+					}
+				}
+				Ok(BinaryArraySet::from_sorted_vec(elems))
+			}
+		}
+
+		deserializer.deserialize_seq(SetVisitor(std::marker::PhantomData))
+	}
+
 }
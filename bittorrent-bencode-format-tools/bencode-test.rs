@@ -24,6 +24,7 @@
 use std::collections::BTreeMap;
 use std::io;
 use std::io::ErrorKind;
+use serde::{Serialize, Deserialize};
 mod bencode;
 use bencode::Bencode;
 use bencode::Bencode::{Int, Bytes, List, Dict};
@@ -34,7 +35,7 @@ fn main() {
 	test_serialize_byte_string();
 	test_serialize_list();
 	test_serialize_dictionary();
-	
+
 	test_parse_empty();
 	test_parse_invalid();
 	test_parse_integer();
@@ -48,6 +49,18 @@ fn main() {
 	test_parse_dictionary();
 	test_parse_dictionary_eof();
 	test_parse_dictionary_invalid();
+
+	test_serde_roundtrip_struct();
+	test_serde_dict_key_order();
+	test_serde_reject_float();
+
+	test_event_reader_matches_tree();
+	test_event_reader_streams_large_byte_string();
+	test_event_reader_empty_byte_strings();
+
+	test_cbor_roundtrip();
+	test_cbor_reject_float();
+	test_cbor_reject_indefinite_length();
 }
 
 
@@ -84,7 +97,7 @@ fn test_serialize_dictionary() {
 	check_serialize("de", &Dict(BTreeMap::new()));
 	{
 		let mut d = BTreeMap::<Vec<u8>,Bencode>::new();
-		d.insert(Vec::from(&""[..]), List(vec![]));
+		d.insert(Vec::from(""), List(vec![]));
 		check_serialize("d0:lee", &Dict(d));
 	}
 	{
@@ -280,3 +293,137 @@ fn parse_expecting_exception(testcases: &[&str], expect: io::ErrorKind) {
 		assert_eq!(expect, err.kind());
 	}
 }
+
+
+
+/*---- Test the Serde integration ----*/
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct TorrentInfo {
+	name: String,
+	#[serde(rename = "piece length")]
+	piece_length: i64,
+	// Without this annotation, serde has no way to tell this Vec<u8> apart from
+	// a Vec<i64> and would serialize it as a List of Ints rather than a Bytes.
+	#[serde(with = "serde_bytes")]
+	pieces: Vec<u8>,
+}
+
+
+// Also exercises the #[serde(with = "serde_bytes")] annotation that byte-string
+// fields need: plain Vec<u8> fields map to bencode List (see ser.rs), not Bytes.
+fn test_serde_roundtrip_struct() {
+	let info = TorrentInfo {
+		name: "example.iso".to_string(),
+		piece_length: 262144,
+		pieces: Vec::from(&b"0123456789"[..]),
+	};
+	let bytes: Vec<u8> = bencode::ser::to_bytes(&info).unwrap();
+	assert_eq!(
+		"d4:name11:example.iso12:piece lengthi262144e6:pieces10:0123456789e".as_bytes(),
+		&bytes[..]);
+	let decoded: TorrentInfo = bencode::de::from_bytes(&bytes).unwrap();
+	assert_eq!(info, decoded);
+}
+
+
+fn test_serde_dict_key_order() {
+	// serde_derive always emits fields in declaration order, but bencode::ser
+	// must re-sort them into ascending key order regardless of field order.
+	#[derive(Serialize)]
+	struct OutOfOrder {
+		zebra: i64,
+		apple: i64,
+	}
+	let bytes = bencode::ser::to_bytes(&OutOfOrder { zebra: 1, apple: 2 }).unwrap();
+	assert_eq!("d5:applei2e5:zebrai1ee".as_bytes(), &bytes[..]);
+}
+
+
+fn test_serde_reject_float() {
+	let result = bencode::ser::to_bytes(&1.23456_f64);
+	assert!(result.is_err());
+}
+
+
+
+/*---- Test the streaming event reader ----*/
+
+use bencode::event::{EventReader, Event};
+
+fn test_event_reader_matches_tree() {
+	let s = "d3:fool4:spami-2eee";
+	let tree = try_parse(s).unwrap();
+	let folded = bencode::event::to_bencode(s.as_bytes()).unwrap();
+	assert_eq!(tree, folded);
+}
+
+
+fn test_event_reader_streams_large_byte_string() {
+	let payload = vec![0x42u8; 200_000];
+	let mut encoded = format!("{}:", payload.len()).into_bytes();
+	encoded.extend_from_slice(&payload);
+
+	let mut reader = EventReader::new(&encoded[..]);
+	match reader.next_event().unwrap() {
+		Some(Event::BytesStart(len)) => assert_eq!(payload.len(), len),
+		other => panic!("Expected BytesStart, got {:?}", other),
+	}
+	let mut collected = Vec::new();
+	while let Some(Event::BytesChunk(chunk)) = reader.next_event().unwrap() {
+		assert!(chunk.len() <= 64 * 1024, "Chunk exceeded the bounded size");
+		collected.extend_from_slice(&chunk);
+		if collected.len() == payload.len() {
+			break;
+		}
+	}
+	assert_eq!(payload, collected);
+}
+
+
+fn test_event_reader_empty_byte_strings() {
+	// Empty byte string at top level: no BytesChunk event should follow BytesStart(0).
+	assert_eq!(Bencode::Bytes(Vec::new()), bencode::event::to_bencode("0:".as_bytes()).unwrap());
+
+	// Empty byte string as a list element, alongside a non-empty one.
+	assert_eq!(try_parse("l0:3:fooe").unwrap(),
+		bencode::event::to_bencode("l0:3:fooe".as_bytes()).unwrap());
+
+	// Empty byte string as both a dictionary key and a dictionary value.
+	assert_eq!(try_parse("d0:0:e").unwrap(),
+		bencode::event::to_bencode("d0:0:e".as_bytes()).unwrap());
+	assert_eq!(try_parse("d1:a0:e").unwrap(),
+		bencode::event::to_bencode("d1:a0:e".as_bytes()).unwrap());
+}
+
+
+
+/*---- Test the CBOR transcoding bridge ----*/
+
+fn test_cbor_roundtrip() {
+	let mut d = BTreeMap::<Vec<u8>,Bencode>::new();
+	d.insert(Vec::from(&b"length"[..]), Int(262144));
+	d.insert(Vec::from(&b"name"[..]), Bytes(Vec::from(&b"example.iso"[..])));
+	let value = List(vec![Int(-5), Dict(d), Bytes(vec![])]);
+
+	let mut cbor = Vec::<u8>::new();
+	value.to_cbor(&mut cbor).unwrap();
+	let decoded = Bencode::from_cbor(&mut &cbor[..]).unwrap();
+	assert_eq!(value, decoded);
+}
+
+
+fn test_cbor_reject_float() {
+	// Major type 7, additional info 27: an IEEE 754 double-precision float.
+	let cbor: &[u8] = &[0xfb, 0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+	let result = Bencode::from_cbor(&mut &cbor[..]);
+	assert_eq!(ErrorKind::InvalidData, result.unwrap_err().kind());
+}
+
+
+fn test_cbor_reject_indefinite_length() {
+	// Major type 2 (byte string), additional info 31: indefinite length.
+	let cbor: &[u8] = &[0x5f, 0xff];
+	let result = Bencode::from_cbor(&mut &cbor[..]);
+	assert_eq!(ErrorKind::InvalidData, result.unwrap_err().kind());
+}
@@ -0,0 +1,270 @@
+/*
+ * BitTorrent bencode coder (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::ErrorKind;
+
+pub mod ser;
+pub mod de;
+pub mod error;
+pub mod event;
+pub mod cbor;
+
+// Re-exported so library consumers can write bencode::Error instead of
+// bencode::error::Error. Unused within this crate's own test binaries, which
+// reach ser::Result/de::Result through their own code paths instead.
+#[allow(unused_imports)]
+pub use error::Error;
+
+
+// Represents a bencode value, which is either an integer, a byte string,
+// a list of values, or a dictionary mapping byte strings to values.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Bencode {
+	Int(i64),
+	Bytes(Vec<u8>),
+	List(Vec<Bencode>),
+	Dict(BTreeMap<Vec<u8>,Bencode>),
+}
+
+
+impl Bencode {
+
+	// Losslessly transcodes this bencode value to RFC 8949 CBOR, written to 'out'.
+	pub fn to_cbor(&self, out: &mut dyn io::Write) -> io::Result<()> {
+		cbor::to_writer(self, out)
+	}
+
+
+	// Reads exactly one CBOR item from 'inp' and transcodes it to a bencode value,
+	// rejecting anything bencode cannot represent (see bencode::cbor for the list).
+	pub fn from_cbor(inp: &mut dyn io::Read) -> io::Result<Self> {
+		cbor::from_reader(inp)
+	}
+
+
+	// Writes this bencode value to the given writer in canonical bencode form.
+	pub fn serialize(&self, out: &mut dyn io::Write) -> io::Result<()> {
+		match self {
+			Bencode::Int(n) => write!(out, "i{}e", n),
+
+			Bencode::Bytes(bs) => {
+				write!(out, "{}:", bs.len())?;
+				out.write_all(bs)
+			},
+
+			Bencode::List(vals) => {
+				out.write_all(b"l")?;
+				for val in vals {
+					val.serialize(out)?;
+				}
+				out.write_all(b"e")
+			},
+
+			Bencode::Dict(map) => {
+				out.write_all(b"d")?;
+				// BTreeMap<Vec<u8>,_> already iterates in ascending byte order,
+				// which is exactly the canonical key order that bencode requires.
+				for (key, val) in map {
+					write!(out, "{}:", key.len())?;
+					out.write_all(key)?;
+					val.serialize(out)?;
+				}
+				out.write_all(b"e")
+			},
+		}
+	}
+
+
+	// Reads exactly one bencode value from the given reader, and ensures
+	// that no trailing data follows it. The whole value is parsed eagerly.
+	pub fn parse(inp: &mut dyn io::Read) -> io::Result<Self> {
+		let first: u8 = read_byte(inp)?
+			.ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Empty input"))?;
+		let result = parse_value(inp, first)?;
+		match read_byte(inp)? {
+			None => Ok(result),
+			Some(_) => Err(io::Error::new(ErrorKind::InvalidData, "Trailing data after value")),
+		}
+	}
+
+}
+
+
+// Parses one bencode value, given that 'first' is the already-consumed first byte of it.
+fn parse_value(inp: &mut dyn io::Read, first: u8) -> io::Result<Bencode> {
+	match first {
+		b'i' => parse_integer(inp).map(Bencode::Int),
+		b'l' => parse_list(inp),
+		b'd' => parse_dict(inp),
+		b'0' ..= b'9' => parse_byte_string(inp, first).map(Bencode::Bytes),
+		_ => Err(io::Error::new(ErrorKind::InvalidData, "Unexpected byte")),
+	}
+}
+
+
+// Parses the digits and terminating 'e' of an integer, given that the
+// leading 'i' has already been consumed. Rejects non-canonical forms
+// such as leading zeros, a bare "-0", and non-digit characters.
+fn parse_integer(inp: &mut dyn io::Read) -> io::Result<i64> {
+	let mut negative = false;
+	let mut first: u8 = need_byte(inp)?;
+	if first == b'-' {
+		negative = true;
+		first = need_byte(inp)?;
+	}
+	if !first.is_ascii_digit() {
+		return Err(io::Error::new(ErrorKind::InvalidData, "Expected digit in integer"));
+	}
+
+	if first == b'0' {
+		// "-0" can never be canonical, regardless of what follows, so reject it
+		// without reading further. A lone "0" is canonical only when it is the
+		// entire magnitude; any further digit would be a non-canonical leading zero.
+		if negative {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Negative zero is not allowed"));
+		}
+		let next = need_byte(inp)?;
+		if next.is_ascii_digit() {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Leading zero in integer"));
+		} else if next != b'e' {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Expected 'e' after integer"));
+		}
+		return Ok(0);
+	}
+
+	let mut digits: Vec<u8> = vec![first];
+	loop {
+		let b = need_byte(inp)?;
+		if b == b'e' {
+			break;
+		} else if b.is_ascii_digit() {
+			digits.push(b);
+		} else {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Expected digit or 'e' in integer"));
+		}
+	}
+
+	let mut text = String::with_capacity(digits.len() + 1);
+	if negative {
+		text.push('-');
+	}
+	text.push_str(std::str::from_utf8(&digits).unwrap());
+	text.parse::<i64>()
+		.map_err(|_| io::Error::new(ErrorKind::InvalidData, "Integer out of range"))
+}
+
+
+// Parses the length, colon, and raw bytes of a byte string, given that
+// 'first' is the already-consumed first length digit.
+fn parse_byte_string(inp: &mut dyn io::Read, first: u8) -> io::Result<Vec<u8>> {
+	let len = parse_byte_string_length(inp, first)?;
+	let mut result = vec![0u8; len];
+	inp.read_exact(&mut result)?;
+	Ok(result)
+}
+
+
+// Parses just the length and terminating colon of a byte string (not the data
+// that follows), given that 'first' is the already-consumed first length digit.
+// Used directly by the streaming event reader, which reads the data itself in
+// bounded-size chunks rather than all at once.
+pub(crate) fn parse_byte_string_length(inp: &mut dyn io::Read, first: u8) -> io::Result<usize> {
+	let mut digits: Vec<u8> = vec![first];
+	if first == b'0' {
+		let next = need_byte(inp)?;
+		if next.is_ascii_digit() {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Leading zero in byte string length"));
+		} else if next != b':' {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Expected ':' after byte string length"));
+		}
+	} else {
+		loop {
+			let b = need_byte(inp)?;
+			if b == b':' {
+				break;
+			} else if b.is_ascii_digit() {
+				digits.push(b);
+			} else {
+				return Err(io::Error::new(ErrorKind::InvalidData, "Expected digit or ':' in byte string length"));
+			}
+		}
+	}
+
+	std::str::from_utf8(&digits).unwrap().parse()
+		.map_err(|_| io::Error::new(ErrorKind::InvalidData, "Byte string length out of range"))
+}
+
+
+fn parse_list(inp: &mut dyn io::Read) -> io::Result<Bencode> {
+	let mut result: Vec<Bencode> = Vec::new();
+	loop {
+		let b = need_byte(inp)?;
+		if b == b'e' {
+			return Ok(Bencode::List(result));
+		}
+		result.push(parse_value(inp, b)?);
+	}
+}
+
+
+fn parse_dict(inp: &mut dyn io::Read) -> io::Result<Bencode> {
+	let mut result = BTreeMap::<Vec<u8>,Bencode>::new();
+	let mut last_key: Option<Vec<u8>> = None;
+	loop {
+		let b = need_byte(inp)?;
+		if b == b'e' {
+			return Ok(Bencode::Dict(result));
+		} else if !b.is_ascii_digit() {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Expected byte string key in dictionary"));
+		}
+
+		let key = parse_byte_string(inp, b)?;
+		if let Some(prev) = &last_key {
+			if key <= *prev {
+				return Err(io::Error::new(ErrorKind::InvalidData, "Dictionary keys out of order"));
+			}
+		}
+		let valfirst = need_byte(inp)?;
+		let val = parse_value(inp, valfirst)?;
+		last_key = Some(key.clone());
+		result.insert(key, val);
+	}
+}
+
+
+// Reads a single byte, returning None at end of stream.
+fn read_byte(inp: &mut dyn io::Read) -> io::Result<Option<u8>> {
+	let mut buf = [0u8; 1];
+	match inp.read(&mut buf)? {
+		0 => Ok(None),
+		_ => Ok(Some(buf[0])),
+	}
+}
+
+
+// Reads a single byte, mapping end of stream to an UnexpectedEof error.
+fn need_byte(inp: &mut dyn io::Read) -> io::Result<u8> {
+	read_byte(inp)?.ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of bencode data"))
+}
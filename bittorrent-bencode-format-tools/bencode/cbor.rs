@@ -0,0 +1,211 @@
+/*
+ * BitTorrent bencode coder (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::ErrorKind;
+use super::Bencode;
+
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES:    u8 = 2;
+const MAJOR_TEXT:     u8 = 3;
+const MAJOR_ARRAY:    u8 = 4;
+const MAJOR_MAP:      u8 = 5;
+const MAJOR_TAG:      u8 = 6;
+const MAJOR_SIMPLE:   u8 = 7;
+
+
+// Writes this bencode value as RFC 8949 CBOR, written incrementally to 'out'.
+// Int maps to major type 0 or 1, Bytes to major type 2, List to major type 4,
+// and Dict to major type 5 with byte-string keys (already in ascending order,
+// since BTreeMap keeps them that way).
+pub fn to_writer(value: &Bencode, out: &mut dyn io::Write) -> io::Result<()> {
+	match value {
+		Bencode::Int(n) if *n >= 0 => write_header(MAJOR_UNSIGNED, *n as u64, out),
+
+		Bencode::Int(n) => {
+			let arg: u64 = (-1 - (*n as i128)) as u64;
+			write_header(MAJOR_NEGATIVE, arg, out)
+		},
+
+		Bencode::Bytes(bs) => {
+			write_header(MAJOR_BYTES, bs.len() as u64, out)?;
+			out.write_all(bs)
+		},
+
+		Bencode::List(vals) => {
+			write_header(MAJOR_ARRAY, vals.len() as u64, out)?;
+			for val in vals {
+				to_writer(val, out)?;
+			}
+			Ok(())
+		},
+
+		Bencode::Dict(map) => {
+			write_header(MAJOR_MAP, map.len() as u64, out)?;
+			for (key, val) in map {
+				write_header(MAJOR_BYTES, key.len() as u64, out)?;
+				out.write_all(key)?;
+				to_writer(val, out)?;
+			}
+			Ok(())
+		},
+	}
+}
+
+
+// Writes a CBOR major-type/argument header, choosing the shortest encoding
+// for 'arg' as RFC 8949 requires (direct for 0..=23, then 1/2/4/8-byte forms).
+fn write_header(major: u8, arg: u64, out: &mut dyn io::Write) -> io::Result<()> {
+	let top = major << 5;
+	if arg < 24 {
+		out.write_all(&[top | (arg as u8)])
+	} else if arg <= 0xff {
+		out.write_all(&[top | 24, arg as u8])
+	} else if arg <= 0xffff {
+		out.write_all(&[top | 25])?;
+		out.write_all(&(arg as u16).to_be_bytes())
+	} else if arg <= 0xffff_ffff {
+		out.write_all(&[top | 26])?;
+		out.write_all(&(arg as u32).to_be_bytes())
+	} else {
+		out.write_all(&[top | 27])?;
+		out.write_all(&arg.to_be_bytes())
+	}
+}
+
+
+// Reads exactly one CBOR item from 'inp' and transcodes it into a Bencode
+// value, rejecting any input that bencode cannot represent: floats and other
+// major-7 simple values (including booleans and null), text strings, tags,
+// and indefinite-length items. Ensures no trailing data follows the item.
+pub fn from_reader(inp: &mut dyn io::Read) -> io::Result<Bencode> {
+	let result = parse_value(inp)?;
+	match read_byte(inp)? {
+		None => Ok(result),
+		Some(_) => Err(io::Error::new(ErrorKind::InvalidData, "Trailing data after value")),
+	}
+}
+
+
+fn parse_value(inp: &mut dyn io::Read) -> io::Result<Bencode> {
+	let first = need_byte(inp)?;
+	let major = first >> 5;
+	let info = first & 0x1f;
+
+	match major {
+		MAJOR_UNSIGNED => {
+			let arg = read_definite_length(inp, info, "integers")?;
+			let n: i64 = arg.try_into()
+				.map_err(|_| io::Error::new(ErrorKind::InvalidData, "Integer too large for bencode"))?;
+			Ok(Bencode::Int(n))
+		},
+
+		MAJOR_NEGATIVE => {
+			let arg = read_definite_length(inp, info, "integers")?;
+			let n: i128 = -1 - (arg as i128);
+			let n: i64 = n.try_into()
+				.map_err(|_| io::Error::new(ErrorKind::InvalidData, "Integer too large for bencode"))?;
+			Ok(Bencode::Int(n))
+		},
+
+		MAJOR_BYTES => {
+			let len = read_definite_length(inp, info, "byte strings")?;
+			Ok(Bencode::Bytes(read_exact_bytes(inp, len)?))
+		},
+
+		MAJOR_TEXT => Err(io::Error::new(ErrorKind::InvalidData, "Bencode cannot represent CBOR text strings")),
+
+		MAJOR_ARRAY => {
+			let len = read_definite_length(inp, info, "arrays")?;
+			let mut items = Vec::with_capacity(len.min(1 << 20) as usize);
+			for _ in 0 .. len {
+				items.push(parse_value(inp)?);
+			}
+			Ok(Bencode::List(items))
+		},
+
+		MAJOR_MAP => {
+			let len = read_definite_length(inp, info, "maps")?;
+			let mut map = BTreeMap::<Vec<u8>,Bencode>::new();
+			for _ in 0 .. len {
+				let key = match parse_value(inp)? {
+					Bencode::Bytes(b) => b,
+					_ => return Err(io::Error::new(ErrorKind::InvalidData, "Bencode map keys must be byte strings")),
+				};
+				let val = parse_value(inp)?;
+				map.insert(key, val);
+			}
+			Ok(Bencode::Dict(map))
+		},
+
+		MAJOR_TAG => Err(io::Error::new(ErrorKind::InvalidData, "Bencode cannot represent CBOR tags")),
+
+		MAJOR_SIMPLE => Err(io::Error::new(ErrorKind::InvalidData,
+			"Bencode cannot represent CBOR simple values, floats, booleans, or null")),
+
+		_ => unreachable!("Major type is a 3-bit field"),
+	}
+}
+
+
+// Reads the argument that follows a header's additional-info field, and
+// rejects both reserved additional-info values and indefinite-length (31),
+// since bencode has no counterpart for CBOR's indefinite-length items.
+fn read_definite_length(inp: &mut dyn io::Read, info: u8, what: &'static str) -> io::Result<u64> {
+	match info {
+		0 ..= 23 => Ok(info as u64),
+		24 => Ok(need_byte(inp)? as u64),
+		25 => { let mut b = [0u8; 2]; inp.read_exact(&mut b)?; Ok(u16::from_be_bytes(b) as u64) },
+		26 => { let mut b = [0u8; 4]; inp.read_exact(&mut b)?; Ok(u32::from_be_bytes(b) as u64) },
+		27 => { let mut b = [0u8; 8]; inp.read_exact(&mut b)?; Ok(u64::from_be_bytes(b)) },
+		28 ..= 30 => Err(io::Error::new(ErrorKind::InvalidData, "Reserved CBOR additional-info value")),
+		31 => Err(io::Error::new(ErrorKind::InvalidData, format!("Bencode cannot represent indefinite-length {}", what))),
+		_ => unreachable!("Additional info is a 5-bit field"),
+	}
+}
+
+
+fn read_exact_bytes(inp: &mut dyn io::Read, len: u64) -> io::Result<Vec<u8>> {
+	let len: usize = len.try_into()
+		.map_err(|_| io::Error::new(ErrorKind::InvalidData, "Length too large"))?;
+	let mut result = vec![0u8; len];
+	inp.read_exact(&mut result)?;
+	Ok(result)
+}
+
+
+fn read_byte(inp: &mut dyn io::Read) -> io::Result<Option<u8>> {
+	let mut buf = [0u8; 1];
+	match inp.read(&mut buf)? {
+		0 => Ok(None),
+		_ => Ok(Some(buf[0])),
+	}
+}
+
+
+fn need_byte(inp: &mut dyn io::Read) -> io::Result<u8> {
+	read_byte(inp)?.ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of CBOR data"))
+}
@@ -0,0 +1,164 @@
+/*
+ * BitTorrent bencode coder (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::collections::btree_map;
+use std::io;
+use std::vec;
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::forward_to_deserialize_any;
+use crate::bencode::Bencode;
+use crate::bencode::error::{Error, Result};
+
+
+// Parses one bencode value from 'reader' (eagerly, via Bencode::parse, so that
+// the existing parser's strictness - canonical integers, sorted unique dict
+// keys, no trailing data - is reused rather than re-implemented) and then lets
+// serde walk the resulting value tree to build the caller's type.
+pub struct Deserializer<R> {
+	reader: R,
+}
+
+
+impl<R: io::Read> Deserializer<R> {
+	pub fn new(reader: R) -> Self {
+		Self { reader }
+	}
+}
+
+
+pub fn from_reader<T: DeserializeOwned, R: io::Read>(reader: R) -> Result<T> {
+	let mut de = Deserializer::new(reader);
+	T::deserialize(&mut de)
+}
+
+
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+	from_reader(bytes)
+}
+
+
+impl<'de, R: io::Read> de::Deserializer<'de> for &mut Deserializer<R> {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		let value = Bencode::parse(&mut self.reader)?;
+		ValueDeserializer { value }.deserialize_any(visitor)
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str],
+			_visitor: V) -> Result<V::Value> {
+		Err(Error::Message("Bencode cannot represent enums".to_string()))
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct identifier ignored_any
+	}
+}
+
+
+// Deserializes from an already-parsed Bencode value, recursively. This is what
+// drives lists and dicts, since a list/dict's elements are themselves values.
+struct ValueDeserializer {
+	value: Bencode,
+}
+
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+		match self.value {
+			Bencode::Int(n) => visitor.visit_i64(n),
+			Bencode::Bytes(b) => visitor.visit_byte_buf(b),
+			Bencode::List(items) => visitor.visit_seq(SeqDeserializer { iter: items.into_iter() }),
+			Bencode::Dict(map) => visitor.visit_map(MapDeserializer { iter: map.into_iter(), value: None }),
+		}
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str],
+			_visitor: V) -> Result<V::Value> {
+		Err(Error::Message("Bencode cannot represent enums".to_string()))
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct identifier ignored_any
+	}
+}
+
+
+struct SeqDeserializer {
+	iter: vec::IntoIter<Bencode>,
+}
+
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+	type Error = Error;
+
+	fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+		match self.iter.next() {
+			None => Ok(None),
+			Some(value) => seed.deserialize(ValueDeserializer { value }).map(Some),
+		}
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		let (lower, upper) = self.iter.size_hint();
+		if upper == Some(lower) { Some(lower) } else { None }
+	}
+}
+
+
+struct MapDeserializer {
+	iter: btree_map::IntoIter<Vec<u8>,Bencode>,
+	value: Option<Bencode>,
+}
+
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+	type Error = Error;
+
+	fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+		match self.iter.next() {
+			None => Ok(None),
+			Some((key, value)) => {
+				self.value = Some(value);
+				seed.deserialize(ValueDeserializer { value: Bencode::Bytes(key) }).map(Some)
+			},
+		}
+	}
+
+	fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+		let value = self.value.take()
+			.ok_or_else(|| Error::Message("next_value_seed called before next_key_seed".to_string()))?;
+		seed.deserialize(ValueDeserializer { value })
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		let (lower, upper) = self.iter.size_hint();
+		if upper == Some(lower) { Some(lower) } else { None }
+	}
+}
@@ -0,0 +1,81 @@
+/*
+ * BitTorrent bencode coder (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::fmt;
+use std::io;
+
+
+// The error type shared by bencode::ser and bencode::de. Wraps I/O
+// failures and reports values/shapes that bencode cannot represent.
+#[derive(Debug)]
+pub enum Error {
+	Io(io::Error),
+	Message(String),
+}
+
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Io(e) => write!(f, "{}", e),
+			Error::Message(s) => write!(f, "{}", s),
+		}
+	}
+}
+
+
+impl std::error::Error for Error {}
+
+
+impl From<io::Error> for Error {
+	fn from(e: io::Error) -> Self {
+		Error::Io(e)
+	}
+}
+
+
+impl From<Error> for io::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error::Io(e) => e,
+			Error::Message(s) => io::Error::new(io::ErrorKind::InvalidData, s),
+		}
+	}
+}
+
+
+impl serde::ser::Error for Error {
+	fn custom<T: fmt::Display>(msg: T) -> Self {
+		Error::Message(msg.to_string())
+	}
+}
+
+
+impl serde::de::Error for Error {
+	fn custom<T: fmt::Display>(msg: T) -> Self {
+		Error::Message(msg.to_string())
+	}
+}
+
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,324 @@
+/*
+ * BitTorrent bencode coder (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::ErrorKind;
+use super::{Bencode, need_byte, parse_integer, parse_byte_string_length};
+
+
+// The largest chunk of a byte string's data that is read into memory at once.
+// Bounds memory use when streaming a multi-gigabyte byte string such as a
+// torrent's 'pieces' field.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+
+// One token of a flattened, SAX-style bencode token stream. Unlike
+// Bencode::parse, an EventReader never materializes a full value tree; a huge
+// byte string surfaces as a BytesStart followed by as many BytesChunk events
+// as needed, each bounded by CHUNK_SIZE.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+	IntValue(i64),
+	BytesStart(usize),
+	BytesChunk(Vec<u8>),
+	ListStart,
+	ListEnd,
+	DictStart,
+	DictEnd,
+}
+
+
+// Tracks what action is owed to the stream right now, so that next_event()
+// can resume in the middle of a multi-chunk byte string or a container.
+enum Pending {
+	// A new value (or a container's closing byte) is expected next.
+	Value,
+	// 'remaining' bytes of the current byte string's data are still to be read.
+	// 'is_key' says whether this string is a dictionary key, in which case its
+	// bytes must also be buffered (not just streamed) so that its ordering
+	// relative to the previous key can be validated once it is complete.
+	Bytes { remaining: usize, is_key: bool },
+}
+
+
+// One level of container nesting that the reader is currently inside.
+enum Frame {
+	List,
+	Dict { last_key: Option<Vec<u8>>, expect_key: bool, current_key: Vec<u8> },
+}
+
+
+// A streaming, SAX-style bencode decoder: it yields a flat sequence of Events
+// instead of building a Bencode value tree, so a caller can process or forward
+// a multi-gigabyte byte string (e.g. a torrent's 'pieces' field) in bounded
+// memory. The same canonical-form invariants that Bencode::parse enforces -
+// canonical integers, sorted unique dictionary keys, exact byte-string
+// lengths - are enforced here as the corresponding events are produced.
+pub struct EventReader<R> {
+	reader: R,
+	stack: Vec<Frame>,
+	pending: Pending,
+	started: bool,
+}
+
+
+impl<R: io::Read> EventReader<R> {
+
+	pub fn new(reader: R) -> Self {
+		Self { reader, stack: Vec::new(), pending: Pending::Value, started: false }
+	}
+
+
+	// Returns the next event, or None once the single top-level value (and
+	// all its descendants) has been fully read. Like Bencode::parse, rejects
+	// any trailing data found after that top-level value.
+	pub fn next_event(&mut self) -> io::Result<Option<Event>> {
+		if let Pending::Bytes { remaining, is_key } = self.pending {
+			return self.next_bytes_chunk(remaining, is_key).map(Some);
+		}
+
+		match self.stack.last() {
+			None if self.started => {
+				// The top-level value is already fully read; only trailing
+				// data (which is an error) or a clean end of stream remains.
+				return match super::read_byte(&mut self.reader)? {
+					None => Ok(None),
+					Some(_) => Err(io::Error::new(ErrorKind::InvalidData, "Trailing data after value")),
+				};
+			},
+			_ => {},
+		}
+
+		// A dictionary entry alternates between reading a key (always a byte
+		// string) and reading a value (any bencode type), or sees the closing
+		// 'e' when a key was expected.
+		let expecting_key = matches!(self.stack.last(), Some(Frame::Dict { expect_key: true, .. }));
+
+		let first = need_byte(&mut self.reader)?;
+		self.started = true;
+
+		if first == b'e' {
+			return self.close_frame();
+		}
+
+		if expecting_key && !first.is_ascii_digit() {
+			return Err(io::Error::new(ErrorKind::InvalidData, "Expected byte string key in dictionary"));
+		}
+
+		match first {
+			b'i' => {
+				let n = parse_integer(&mut self.reader)?;
+				self.after_value_completed();
+				Ok(Some(Event::IntValue(n)))
+			},
+
+			b'0' ..= b'9' => {
+				let len = parse_byte_string_length(&mut self.reader, first)?;
+				if len == 0 {
+					// There is no data to stream, so no BytesChunk event follows:
+					// go straight back to expecting a value, after doing the same
+					// key-ordering bookkeeping that the last chunk of a non-empty
+					// string would trigger in next_bytes_chunk().
+					self.pending = Pending::Value;
+					self.finish_bytes(expecting_key)?;
+				} else {
+					self.pending = Pending::Bytes { remaining: len, is_key: expecting_key };
+				}
+				Ok(Some(Event::BytesStart(len)))
+			},
+
+			b'l' => {
+				if expecting_key {
+					return Err(io::Error::new(ErrorKind::InvalidData, "Expected byte string key in dictionary"));
+				}
+				self.stack.push(Frame::List);
+				Ok(Some(Event::ListStart))
+			},
+
+			b'd' => {
+				if expecting_key {
+					return Err(io::Error::new(ErrorKind::InvalidData, "Expected byte string key in dictionary"));
+				}
+				self.stack.push(Frame::Dict { last_key: None, expect_key: true, current_key: Vec::new() });
+				Ok(Some(Event::DictStart))
+			},
+
+			_ => Err(io::Error::new(ErrorKind::InvalidData, "Unexpected byte")),
+		}
+	}
+
+
+	// Reads up to CHUNK_SIZE bytes of the current byte string's data.
+	fn next_bytes_chunk(&mut self, remaining: usize, is_key: bool) -> io::Result<Event> {
+		let n = remaining.min(CHUNK_SIZE);
+		let mut chunk = vec![0u8; n];
+		self.reader.read_exact(&mut chunk)?;
+
+		if is_key {
+			if let Some(Frame::Dict { current_key, .. }) = self.stack.last_mut() {
+				current_key.extend_from_slice(&chunk);
+			}
+		}
+
+		let remaining = remaining - n;
+		if remaining > 0 {
+			self.pending = Pending::Bytes { remaining, is_key };
+		} else {
+			self.pending = Pending::Value;
+			self.finish_bytes(is_key)?;
+		}
+		Ok(Event::BytesChunk(chunk))
+	}
+
+
+	// Called once a byte string's data has been fully consumed - whether via
+	// the last chunk in next_bytes_chunk(), or directly from next_event() for
+	// a zero-length string that has no chunk at all - to validate a key's
+	// ordering against the previous one and advance the enclosing dictionary
+	// (if any) back to expecting its next key.
+	fn finish_bytes(&mut self, is_key: bool) -> io::Result<()> {
+		if is_key {
+			if let Some(Frame::Dict { last_key, expect_key, current_key }) = self.stack.last_mut() {
+				let key = std::mem::take(current_key);
+				if let Some(prev) = last_key {
+					if key <= *prev {
+						return Err(io::Error::new(ErrorKind::InvalidData, "Dictionary keys out of order"));
+					}
+				}
+				*last_key = Some(key);
+				*expect_key = false;
+			}
+		} else if let Some(Frame::Dict { expect_key, .. }) = self.stack.last_mut() {
+			*expect_key = true;
+		}
+		Ok(())
+	}
+
+
+	// Pops the innermost container and emits its closing event, advancing the
+	// parent (if any) to the state that follows a completed value.
+	fn close_frame(&mut self) -> io::Result<Option<Event>> {
+		match self.stack.pop() {
+			None => Err(io::Error::new(ErrorKind::InvalidData, "Unexpected byte")),
+
+			Some(Frame::List) => {
+				self.after_value_completed();
+				Ok(Some(Event::ListEnd))
+			},
+
+			Some(Frame::Dict { expect_key, .. }) => {
+				if !expect_key {
+					return Err(io::Error::new(ErrorKind::InvalidData, "Dictionary key missing its value"));
+				}
+				self.after_value_completed();
+				Ok(Some(Event::DictEnd))
+			},
+		}
+	}
+
+
+	// Call after a value (of any kind) has just been completed, to advance
+	// the enclosing dictionary (if any) from expecting a value back to
+	// expecting the next key.
+	fn after_value_completed(&mut self) {
+		if let Some(Frame::Dict { expect_key, .. }) = self.stack.last_mut() {
+			*expect_key = true;
+		}
+	}
+
+}
+
+
+// Folds an entire event stream back into a single Bencode value, so that
+// callers who don't need streaming can keep using the original tree-based API.
+pub fn to_bencode<R: io::Read>(reader: R) -> io::Result<Bencode> {
+	let mut events = EventReader::new(reader);
+	let result = fold_value(&mut events)?;
+	match events.next_event()? {
+		None => Ok(result),
+		Some(_) => Err(io::Error::new(ErrorKind::InvalidData, "Trailing data after value")),
+	}
+}
+
+
+fn fold_value<R: io::Read>(events: &mut EventReader<R>) -> io::Result<Bencode> {
+	let event = events.next_event()?
+		.ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of bencode data"))?;
+	fold_from(events, event)
+}
+
+
+// Reads the byte-string data following an already-read BytesStart(len) event.
+fn fold_bytes<R: io::Read>(events: &mut EventReader<R>, len: usize) -> io::Result<Vec<u8>> {
+	let mut bytes = Vec::with_capacity(len);
+	while bytes.len() < len {
+		match events.next_event()? {
+			Some(Event::BytesChunk(chunk)) => bytes.extend_from_slice(&chunk),
+			_ => return Err(io::Error::new(ErrorKind::InvalidData, "Expected byte string chunk")),
+		}
+	}
+	Ok(bytes)
+}
+
+
+// Folds one value into a Bencode tree, given that its first event has
+// already been read (so that list/dict items, whose start event is consumed
+// by the enclosing loop's own next_event() call, can be folded the same way
+// as a top-level value).
+fn fold_from<R: io::Read>(events: &mut EventReader<R>, first: Event) -> io::Result<Bencode> {
+	match first {
+		Event::IntValue(n) => Ok(Bencode::Int(n)),
+
+		Event::BytesStart(len) => fold_bytes(events, len).map(Bencode::Bytes),
+
+		Event::ListStart => {
+			let mut items = Vec::new();
+			loop {
+				match events.next_event()? {
+					Some(Event::ListEnd) => break,
+					Some(ev) => items.push(fold_from(events, ev)?),
+					None => return Err(io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of bencode data")),
+				}
+			}
+			Ok(Bencode::List(items))
+		},
+
+		Event::DictStart => {
+			let mut map = BTreeMap::<Vec<u8>,Bencode>::new();
+			loop {
+				let key = match events.next_event()? {
+					Some(Event::DictEnd) => break,
+					Some(Event::BytesStart(len)) => fold_bytes(events, len)?,
+					_ => return Err(io::Error::new(ErrorKind::InvalidData, "Expected dictionary key")),
+				};
+				let val = fold_value(events)?;
+				map.insert(key, val);
+			}
+			Ok(Bencode::Dict(map))
+		},
+
+		Event::ListEnd | Event::DictEnd | Event::BytesChunk(_) =>
+			Err(io::Error::new(ErrorKind::InvalidData, "Unexpected event")),
+	}
+}
@@ -0,0 +1,379 @@
+/*
+ * BitTorrent bencode coder (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::io;
+use serde::ser::{self, Serialize};
+use crate::bencode::error::{Error, Result};
+
+
+// Serializes a value to canonical bencode bytes, written incrementally to 'writer'.
+// Integers map to Int, strings/bytes map to Bytes, sequences/tuples map to List, and
+// maps/structs map to Dict (buffered and re-sorted into ascending key order, since
+// bencode requires that but serde does not guarantee it from the caller).
+//
+// Caveat: serde has no way to tell a Vec<u8> field apart from, say, a Vec<i64>
+// field at this layer, so a plain Vec<u8> goes through serialize_seq and maps to
+// a List of Ints, not a Bytes. For a field that should map to Bytes (e.g. a
+// torrent's 'pieces'), annotate it with #[serde(with = "serde_bytes")] (or give
+// it type serde_bytes::ByteBuf) so it reaches serialize_bytes instead.
+pub struct Serializer<W> {
+	writer: W,
+}
+
+
+impl<W: io::Write> Serializer<W> {
+	pub fn new(writer: W) -> Self {
+		Self { writer }
+	}
+}
+
+
+pub fn to_writer<T: Serialize, W: io::Write>(value: &T, writer: W) -> Result<()> {
+	let mut ser = Serializer::new(writer);
+	value.serialize(&mut ser)
+}
+
+
+pub fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+	let mut buf = Vec::new();
+	to_writer(value, &mut buf)?;
+	Ok(buf)
+}
+
+
+fn unsupported(what: &'static str) -> Error {
+	Error::Message(format!("Bencode cannot represent {}", what))
+}
+
+
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
+	type Ok = ();
+	type Error = Error;
+	type SerializeSeq = SeqSerializer<'a, W>;
+	type SerializeTuple = SeqSerializer<'a, W>;
+	type SerializeTupleStruct = SeqSerializer<'a, W>;
+	type SerializeTupleVariant = ser::Impossible<(), Error>;
+	type SerializeMap = MapSerializer<'a, W>;
+	type SerializeStruct = MapSerializer<'a, W>;
+	type SerializeStructVariant = ser::Impossible<(), Error>;
+
+	fn serialize_i8(self, v: i8) -> Result<()> { self.serialize_i64(v as i64) }
+	fn serialize_i16(self, v: i16) -> Result<()> { self.serialize_i64(v as i64) }
+	fn serialize_i32(self, v: i32) -> Result<()> { self.serialize_i64(v as i64) }
+
+	fn serialize_i64(self, v: i64) -> Result<()> {
+		write!(self.writer, "i{}e", v)?;
+		Ok(())
+	}
+
+	fn serialize_i128(self, v: i128) -> Result<()> {
+		let v: i64 = v.try_into().map_err(|_| Error::Message("Integer out of range for bencode".to_string()))?;
+		self.serialize_i64(v)
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<()> { self.serialize_i64(v as i64) }
+	fn serialize_u16(self, v: u16) -> Result<()> { self.serialize_i64(v as i64) }
+	fn serialize_u32(self, v: u32) -> Result<()> { self.serialize_i64(v as i64) }
+
+	fn serialize_u64(self, v: u64) -> Result<()> {
+		let v: i64 = v.try_into().map_err(|_| Error::Message("Integer out of range for bencode".to_string()))?;
+		self.serialize_i64(v)
+	}
+
+	fn serialize_u128(self, v: u128) -> Result<()> {
+		let v: i64 = v.try_into().map_err(|_| Error::Message("Integer out of range for bencode".to_string()))?;
+		self.serialize_i64(v)
+	}
+
+	fn serialize_bool(self, _v: bool) -> Result<()> { Err(unsupported("booleans")) }
+	fn serialize_f32(self, _v: f32) -> Result<()> { Err(unsupported("floating-point numbers")) }
+	fn serialize_f64(self, _v: f64) -> Result<()> { Err(unsupported("floating-point numbers")) }
+	fn serialize_char(self, _v: char) -> Result<()> { Err(unsupported("standalone characters")) }
+
+	fn serialize_str(self, v: &str) -> Result<()> {
+		self.serialize_bytes(v.as_bytes())
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+		write!(self.writer, "{}:", v.len())?;
+		self.writer.write_all(v)?;
+		Ok(())
+	}
+
+	fn serialize_none(self) -> Result<()> { Err(unsupported("None")) }
+
+	fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<()> { Err(unsupported("unit")) }
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<()> { Err(unsupported("unit structs")) }
+
+	fn serialize_unit_variant(self, _name: &'static str, _index: u32, _variant: &'static str) -> Result<()> {
+		Err(unsupported("enums"))
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<()> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _index: u32,
+			_variant: &'static str, _value: &T) -> Result<()> {
+		Err(unsupported("enums"))
+	}
+
+	fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+		self.writer.write_all(b"l")?;
+		Ok(SeqSerializer { ser: self })
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str,
+			_len: usize) -> Result<Self::SerializeTupleVariant> {
+		Err(unsupported("enums"))
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+		Ok(MapSerializer { ser: self, entries: Vec::new(), pending_key: None })
+	}
+
+	fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+		self.serialize_map(Some(len))
+	}
+
+	fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str,
+			_len: usize) -> Result<Self::SerializeStructVariant> {
+		Err(unsupported("enums"))
+	}
+}
+
+
+// Lists stream straight through: bencode does not reorder list elements, so each
+// element can be written to the underlying writer as soon as it is serialized.
+pub struct SeqSerializer<'a, W> {
+	ser: &'a mut Serializer<W>,
+}
+
+
+impl<'a, W: io::Write> ser::SerializeSeq for SeqSerializer<'a, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		value.serialize(&mut *self.ser)
+	}
+
+	fn end(self) -> Result<()> {
+		self.ser.writer.write_all(b"e")?;
+		Ok(())
+	}
+}
+
+
+impl<'a, W: io::Write> ser::SerializeTuple for SeqSerializer<'a, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<()> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+
+impl<'a, W: io::Write> ser::SerializeTupleStruct for SeqSerializer<'a, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		ser::SerializeSeq::serialize_element(self, value)
+	}
+
+	fn end(self) -> Result<()> {
+		ser::SerializeSeq::end(self)
+	}
+}
+
+
+// Dicts must buffer their entries: bencode requires keys in ascending sorted order,
+// but serde delivers map/struct entries in insertion order, so sorting must happen
+// once all entries are known, at end().
+pub struct MapSerializer<'a, W> {
+	ser: &'a mut Serializer<W>,
+	entries: Vec<(Vec<u8>,Vec<u8>)>,
+	pending_key: Option<Vec<u8>>,
+}
+
+
+impl<'a, W: io::Write> ser::SerializeMap for MapSerializer<'a, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+		self.pending_key = Some(key.serialize(KeySerializer)?);
+		Ok(())
+	}
+
+	fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+		let key = self.pending_key.take()
+			.ok_or_else(|| Error::Message("serialize_value called before serialize_key".to_string()))?;
+		let mut buf = Vec::new();
+		{
+			let mut ser = Serializer::new(&mut buf);
+			value.serialize(&mut ser)?;
+		}
+		self.entries.push((key, buf));
+		Ok(())
+	}
+
+	fn end(self) -> Result<()> {
+		write_dict(self.ser, self.entries)
+	}
+}
+
+
+impl<'a, W: io::Write> ser::SerializeStruct for MapSerializer<'a, W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<()> {
+		let mut buf = Vec::new();
+		{
+			let mut ser = Serializer::new(&mut buf);
+			value.serialize(&mut ser)?;
+		}
+		self.entries.push((key.as_bytes().to_vec(), buf));
+		Ok(())
+	}
+
+	fn end(self) -> Result<()> {
+		write_dict(self.ser, self.entries)
+	}
+}
+
+
+fn write_dict<W: io::Write>(ser: &mut Serializer<W>, mut entries: Vec<(Vec<u8>,Vec<u8>)>) -> Result<()> {
+	entries.sort_by(|a, b| a.0.cmp(&b.0));
+	for w in entries.windows(2) {
+		if w[0].0 == w[1].0 {
+			return Err(Error::Message("Duplicate dictionary key".to_string()));
+		}
+	}
+	ser.writer.write_all(b"d")?;
+	for (key, value) in &entries {
+		write!(ser.writer, "{}:", key.len())?;
+		ser.writer.write_all(key)?;
+		ser.writer.write_all(value)?;
+	}
+	ser.writer.write_all(b"e")?;
+	Ok(())
+}
+
+
+// Serializes a map/struct key on its own, producing the raw key bytes (not
+// bencode-framed) so that the caller can measure its length and sort by it.
+// Bencode dictionary keys are always byte strings, so only str/bytes, char (as
+// its UTF-8 bytes) and unit enum variants (as the variant name) are accepted.
+struct KeySerializer;
+
+
+impl ser::Serializer for KeySerializer {
+	type Ok = Vec<u8>;
+	type Error = Error;
+	type SerializeSeq = ser::Impossible<Vec<u8>, Error>;
+	type SerializeTuple = ser::Impossible<Vec<u8>, Error>;
+	type SerializeTupleStruct = ser::Impossible<Vec<u8>, Error>;
+	type SerializeTupleVariant = ser::Impossible<Vec<u8>, Error>;
+	type SerializeMap = ser::Impossible<Vec<u8>, Error>;
+	type SerializeStruct = ser::Impossible<Vec<u8>, Error>;
+	type SerializeStructVariant = ser::Impossible<Vec<u8>, Error>;
+
+	fn serialize_str(self, v: &str) -> Result<Vec<u8>> { Ok(v.as_bytes().to_vec()) }
+	fn serialize_bytes(self, v: &[u8]) -> Result<Vec<u8>> { Ok(v.to_vec()) }
+
+	fn serialize_bool(self, _v: bool) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_i8(self, _v: i8) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_i16(self, _v: i16) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_i32(self, _v: i32) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_i64(self, _v: i64) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_i128(self, _v: i128) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_u8(self, _v: u8) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_u16(self, _v: u16) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_u32(self, _v: u32) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_u64(self, _v: u64) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_u128(self, _v: u128) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_f32(self, _v: f32) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_f64(self, _v: f64) -> Result<Vec<u8>> { Err(unsupported("a non-string dictionary key")) }
+	fn serialize_char(self, v: char) -> Result<Vec<u8>> { Ok(v.to_string().into_bytes()) }
+
+	fn serialize_none(self) -> Result<Vec<u8>> { Err(unsupported("a None dictionary key")) }
+	fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Vec<u8>> { value.serialize(self) }
+	fn serialize_unit(self) -> Result<Vec<u8>> { Err(unsupported("a unit dictionary key")) }
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<Vec<u8>> { Err(unsupported("a unit dictionary key")) }
+
+	fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Vec<u8>> {
+		Ok(variant.as_bytes().to_vec())
+	}
+
+	fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<Vec<u8>> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: ?Sized + Serialize>(self, _name: &'static str, _index: u32,
+			_variant: &'static str, _value: &T) -> Result<Vec<u8>> {
+		Err(unsupported("an enum dictionary key"))
+	}
+
+	fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> { Err(unsupported("a sequence dictionary key")) }
+	fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> { Err(unsupported("a tuple dictionary key")) }
+
+	fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+		Err(unsupported("a tuple struct dictionary key"))
+	}
+
+	fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str,
+			_len: usize) -> Result<Self::SerializeTupleVariant> {
+		Err(unsupported("an enum dictionary key"))
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> { Err(unsupported("a map dictionary key")) }
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+		Err(unsupported("a struct dictionary key"))
+	}
+
+	fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str,
+			_len: usize) -> Result<Self::SerializeStructVariant> {
+		Err(unsupported("an enum dictionary key"))
+	}
+}
@@ -0,0 +1,163 @@
+/*
+ * Ethereum RLP (Recursive Length Prefix) coder test suite (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::io;
+use std::io::ErrorKind;
+mod rlp;
+use rlp::Rlp;
+use rlp::Rlp::{Bytes, List};
+
+
+fn main() {
+	test_serialize_byte_string();
+	test_serialize_list();
+	test_encode_uint();
+
+	test_parse_empty();
+	test_parse_byte_string();
+	test_parse_byte_string_eof();
+	test_parse_byte_string_invalid();
+	test_parse_list();
+	test_parse_list_invalid();
+}
+
+
+
+/*---- Test the serialization ----*/
+
+fn test_serialize_byte_string() {
+	check_serialize(&[0x00], &Bytes(vec![0x00]));
+	check_serialize(&[0x7f], &Bytes(vec![0x7f]));
+	check_serialize(&[0x80], &Bytes(vec![]));
+	check_serialize(&[0x81, 0x80], &Bytes(vec![0x80]));
+	check_serialize(&[0x83, b'd', b'o', b'g'], &Bytes(Vec::from(&b"dog"[..])));
+	{
+		let mut expect = vec![0xb7 + 1, 56];
+		let payload = vec![b'x'; 56];
+		expect.extend_from_slice(&payload);
+		check_serialize(&expect, &Bytes(payload));
+	}
+}
+
+
+fn test_serialize_list() {
+	check_serialize(&[0xc0], &List(vec![]));
+	check_serialize(&[0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'],
+		&List(vec![Bytes(Vec::from(&b"cat"[..])), Bytes(Vec::from(&b"dog"[..]))]));
+	{
+		let mut payload = Vec::<u8>::new();
+		for _ in 0 .. 19 {
+			payload.extend_from_slice(&[0x83, b'd', b'o', b'g']);
+		}
+		let mut expect = vec![0xf7 + 1, payload.len() as u8];
+		expect.extend_from_slice(&payload);
+		check_serialize(&expect, &List(vec![Bytes(Vec::from(&b"dog"[..])); 19]));
+	}
+}
+
+
+fn test_encode_uint() {
+	check_serialize(&[0x80], &rlp::encode_uint(0));
+	check_serialize(&[0x0f], &rlp::encode_uint(15));
+	check_serialize(&[0x82, 0x04, 0x00], &rlp::encode_uint(1024));
+}
+
+
+// Asserts that serializing the given RLP value equals the given byte string.
+fn check_serialize(expected: &[u8], obj: &Rlp) {
+	let mut actual = Vec::<u8>::new();
+	Rlp::serialize(obj, &mut actual).unwrap();
+	assert_eq!(expected, &actual[..]);
+}
+
+
+
+/*---- Test the parsing ----*/
+
+fn test_parse_empty() {
+	parse_expecting_exception(&[&[]], ErrorKind::UnexpectedEof);
+}
+
+
+fn test_parse_byte_string() {
+	check_parse(&Bytes(vec![0x00]), &[0x00]);
+	check_parse(&Bytes(vec![0x7f]), &[0x7f]);
+	check_parse(&Bytes(vec![]), &[0x80]);
+	check_parse(&Bytes(vec![0x80]), &[0x81, 0x80]);
+	check_parse(&Bytes(Vec::from(&b"dog"[..])), &[0x83, b'd', b'o', b'g']);
+}
+
+
+fn test_parse_byte_string_eof() {
+	parse_expecting_exception(&[
+		&[0x81],
+		&[0x83, b'd', b'o'],
+		&[0xb8, 56],
+	], ErrorKind::UnexpectedEof);
+}
+
+
+fn test_parse_byte_string_invalid() {
+	parse_expecting_exception(&[
+		&[0x81, 0x05],             // Should have been encoded as just [0x05]
+		&[0xb8, 0x00],             // Long-form length byte is a leading zero (length 0 fits the short form)
+	], ErrorKind::InvalidData);
+}
+
+
+fn test_parse_list() {
+	check_parse(&List(vec![]), &[0xc0]);
+	check_parse(&List(vec![Bytes(Vec::from(&b"cat"[..])), Bytes(Vec::from(&b"dog"[..]))]),
+		&[0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g']);
+}
+
+
+fn test_parse_list_invalid() {
+	// A list payload of 10 bytes fits the short form; the long form is non-canonical.
+	let mut data = vec![0xf8, 10];
+	data.extend_from_slice(&[0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g', 0, 0]);
+	parse_expecting_exception(&[&data[..]], ErrorKind::InvalidData);
+}
+
+
+// Asserts that parsing the given byte string equals the given RLP value.
+fn check_parse(expect: &Rlp, s: &[u8]) {
+	let actual = try_parse(s).unwrap();
+	assert_eq!(*expect, actual);
+}
+
+
+// Parses the given byte string into an RLP value.
+fn try_parse(s: &[u8]) -> io::Result<Rlp> {
+	Rlp::parse(Box::new(s).as_mut())
+}
+
+
+// Asserts that parsing each given test case will return the given exception.
+fn parse_expecting_exception(testcases: &[&[u8]], expect: io::ErrorKind) {
+	for cs in testcases {
+		let actual = try_parse(cs);
+		let err = actual.unwrap_err();
+		assert_eq!(expect, err.kind());
+	}
+}
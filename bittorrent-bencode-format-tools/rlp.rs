@@ -0,0 +1,192 @@
+/*
+ * Ethereum RLP (Recursive Length Prefix) coder (Rust)
+ *
+ * Copyright (c) 2020 Project Nayuki. (MIT License)
+ * https://www.nayuki.io/page/bittorrent-bencode-format-tools
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ * - The above copyright notice and this permission notice shall be included in
+ *   all copies or substantial portions of the Software.
+ * - The Software is provided "as is", without warranty of any kind, express or
+ *   implied, including but not limited to the warranties of merchantability,
+ *   fitness for a particular purpose and noninfringement. In no event shall the
+ *   authors or copyright holders be liable for any claim, damages or other
+ *   liability, whether in an action of contract, tort or otherwise, arising from,
+ *   out of or in connection with the Software or the use or other dealings in the
+ *   Software.
+ */
+
+use std::io;
+use std::io::ErrorKind;
+
+
+// Represents an RLP value, which is either a byte string or a list of values.
+// This is the same shape as bencode's Bytes/List (minus Int/Dict, which RLP
+// does not have natively: integers are encoded as minimal-length byte strings).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Rlp {
+	Bytes(Vec<u8>),
+	List(Vec<Rlp>),
+}
+
+
+impl Rlp {
+
+	// Writes this RLP value to the given writer in canonical RLP form.
+	pub fn serialize(&self, out: &mut dyn io::Write) -> io::Result<()> {
+		match self {
+			Rlp::Bytes(bs) => serialize_bytes(bs, out),
+			Rlp::List(vals) => {
+				let mut payload = Vec::<u8>::new();
+				for val in vals {
+					val.serialize(&mut payload)?;
+				}
+				serialize_length(0xc0, 0xf7, payload.len(), out)?;
+				out.write_all(&payload)
+			},
+		}
+	}
+
+
+	// Reads exactly one RLP value from the given reader, and ensures
+	// that no trailing data follows it. The whole value is parsed eagerly.
+	pub fn parse(inp: &mut dyn io::Read) -> io::Result<Self> {
+		let first: u8 = read_byte(inp)?
+			.ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "Empty input"))?;
+		let result = parse_value(inp, first)?;
+		match read_byte(inp)? {
+			None => Ok(result),
+			Some(_) => Err(io::Error::new(ErrorKind::InvalidData, "Trailing data after value")),
+		}
+	}
+
+}
+
+
+// Encodes a non-negative integer as its minimal-length big-endian byte string
+// (no leading zero bytes; zero itself encodes as the empty byte string), then
+// wraps that in the standard RLP byte-string encoding.
+pub fn encode_uint(val: u64) -> Rlp {
+	let mut bytes = val.to_be_bytes().to_vec();
+	while bytes.first() == Some(&0) {
+		bytes.remove(0);
+	}
+	Rlp::Bytes(bytes)
+}
+
+
+fn serialize_bytes(bs: &[u8], out: &mut dyn io::Write) -> io::Result<()> {
+	if bs.len() == 1 && bs[0] <= 0x7f {
+		out.write_all(bs)
+	} else {
+		serialize_length(0x80, 0xb7, bs.len(), out)?;
+		out.write_all(bs)
+	}
+}
+
+
+// Writes the length prefix shared by both byte strings and lists: 'shortbase' + len
+// for len <= 55, otherwise 'longbase' + len-of-len followed by the big-endian length.
+fn serialize_length(shortbase: u8, longbase: u8, len: usize, out: &mut dyn io::Write) -> io::Result<()> {
+	if len <= 55 {
+		out.write_all(&[shortbase + len as u8])
+	} else {
+		let mut lenbytes = (len as u64).to_be_bytes().to_vec();
+		while lenbytes.first() == Some(&0) {
+			lenbytes.remove(0);
+		}
+		out.write_all(&[longbase + lenbytes.len() as u8])?;
+		out.write_all(&lenbytes)
+	}
+}
+
+
+fn parse_value(inp: &mut dyn io::Read, first: u8) -> io::Result<Rlp> {
+	match first {
+		0x00 ..= 0x7f => Ok(Rlp::Bytes(vec![first])),
+
+		0x80 ..= 0xb7 => {
+			let len = (first - 0x80) as usize;
+			let bytes = read_exact_bytes(inp, len)?;
+			if bytes.len() == 1 && bytes[0] <= 0x7f {
+				return Err(io::Error::new(ErrorKind::InvalidData,
+					"Non-canonical encoding: single byte below 0x80 must encode as itself"));
+			}
+			Ok(Rlp::Bytes(bytes))
+		},
+
+		0xb8 ..= 0xbf => {
+			let len = parse_long_length(inp, first - 0xb7)?;
+			Ok(Rlp::Bytes(read_exact_bytes(inp, len)?))
+		},
+
+		0xc0 ..= 0xf7 => {
+			let len = (first - 0xc0) as usize;
+			parse_list_payload(inp, len)
+		},
+
+		0xf8 ..= 0xff => {
+			let len = parse_long_length(inp, first - 0xf7)?;
+			if len <= 55 {
+				return Err(io::Error::new(ErrorKind::InvalidData,
+					"Long-form list used where short form fits"));
+			}
+			parse_list_payload(inp, len)
+		},
+	}
+}
+
+
+// Parses the big-endian length that follows a long-form prefix, given the
+// number of length bytes. Rejects a leading zero byte (non-canonical) and a
+// length that would itself have fit in the short form (<= 55).
+fn parse_long_length(inp: &mut dyn io::Read, num_len_bytes: u8) -> io::Result<usize> {
+	let lenbytes = read_exact_bytes(inp, num_len_bytes as usize)?;
+	if lenbytes[0] == 0 {
+		return Err(io::Error::new(ErrorKind::InvalidData, "Non-canonical length-of-length (leading zero byte)"));
+	}
+	let mut len: u128 = 0;
+	for b in &lenbytes {
+		len = (len << 8) | (*b as u128);
+	}
+	let len: usize = len.try_into()
+		.map_err(|_| io::Error::new(ErrorKind::InvalidData, "Length too large"))?;
+	if len <= 55 {
+		return Err(io::Error::new(ErrorKind::InvalidData, "Non-canonical length: long form used where short form fits"));
+	}
+	Ok(len)
+}
+
+
+fn parse_list_payload(inp: &mut dyn io::Read, len: usize) -> io::Result<Rlp> {
+	let payload = read_exact_bytes(inp, len)?;
+	let mut cursor: &[u8] = &payload;
+	let mut items = Vec::<Rlp>::new();
+	while !cursor.is_empty() {
+		let first = cursor[0];
+		cursor = &cursor[1..];
+		items.push(parse_value(&mut cursor, first)?);
+	}
+	Ok(Rlp::List(items))
+}
+
+
+fn read_exact_bytes(inp: &mut dyn io::Read, len: usize) -> io::Result<Vec<u8>> {
+	let mut result = vec![0u8; len];
+	inp.read_exact(&mut result)?;
+	Ok(result)
+}
+
+
+fn read_byte(inp: &mut dyn io::Read) -> io::Result<Option<u8>> {
+	let mut buf = [0u8; 1];
+	match inp.read(&mut buf)? {
+		0 => Ok(None),
+		_ => Ok(Some(buf[0])),
+	}
+}